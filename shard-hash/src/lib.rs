@@ -32,9 +32,24 @@
 //! ```
 
 use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
+use std::hash::{BuildHasher, Hash, Hasher};
 use std::iter::Iterator;
 
+/// Statistical distribution-quality tests for shard assignment. Enable the
+/// `dev` feature to validate a custom `Hasher` choice against the crate's
+/// even-load-distribution guarantee.
+#[cfg(feature = "dev")]
+pub mod quality;
+
+/// Largest shard `count` for which the narrow `u64` Lehmer state has enough
+/// entropy for a uniform permutation (`20! < u64::MAX < 21!`).
+///
+/// Past this, [`ShardHasher::into_sized_iter`](struct.ShardHasher.html#method.into_sized_iter)
+/// and [`ShardIterator::new`](struct.ShardIterator.html#method.new) skew
+/// toward low shard IDs; [`ShardHasher::hash_one`](struct.ShardHasher.html#method.hash_one)
+/// and [`shards_for`] route around this automatically.
+pub const NARROW_COUNT_LIMIT: u64 = 20;
+
 /// Generic Hasher that can be transformed into a [`ShardIterator`](struct.ShardIterator.html).
 #[derive(Copy, Clone)]
 pub struct ShardHasher<H: Hasher + Sized> {
@@ -44,7 +59,7 @@ pub struct ShardHasher<H: Hasher + Sized> {
 
 impl ShardHasher<DefaultHasher> {
     /// Create a new ShardHasher using [`DefaultHasher`](/nightly/std/collections/hash_map/struct.DefaultHasher.html).
-    /// 
+    ///
     /// `count` correspond to the total number of shards in the system.
     pub fn new(count: u64) -> Self {
         Self {
@@ -52,11 +67,62 @@ impl ShardHasher<DefaultHasher> {
             hasher: DefaultHasher::default(),
         }
     }
+}
+
+impl<H: Hasher + Sized> ShardHasher<H> {
+    /// Create a new `ShardHasher` wrapping an arbitrary `Hasher`.
+    ///
+    /// This allows callers to pin a specific hashing algorithm (e.g.
+    /// `SipHasher13` with fixed keys, or a fast non-cryptographic hasher
+    /// such as `ahash::AHasher`) instead of relying on `DefaultHasher`'s
+    /// unspecified output, which is useful when shard placement needs to
+    /// be stable across processes or machines.
+    ///
+    /// `count` correspond to the total number of shards in the system.
+    pub fn with_hasher(count: u64, hasher: H) -> Self {
+        Self { count, hasher }
+    }
 
     /// Create a [`ShardIterator`](struct.ShardIterator.html) that will only return `size` elements.
+    ///
+    /// Only uniform for `count <= `[`NARROW_COUNT_LIMIT`]; past that, prefer
+    /// [`into_sized_iter_wide`](#method.into_sized_iter_wide), or use
+    /// [`hash_one`](#method.hash_one)/[`shards_for`], which pick the right
+    /// one automatically.
     pub fn into_sized_iter(self, size: u64) -> ShardIterator {
         ShardIterator::new(self.finish(), self.count, size)
     }
+
+    /// Like [`into_sized_iter`](#method.into_sized_iter), but backed by a widened
+    /// permutation state that keeps generating entropy instead of running dry.
+    ///
+    /// A `u64` Lehmer state only covers `20!` distinct permutations, so for
+    /// `count` above [`NARROW_COUNT_LIMIT`] the later digits of `into_sized_iter` are
+    /// starved of entropy and skew toward low shard IDs. Use this variant
+    /// when `count` is large.
+    pub fn into_sized_iter_wide(self, size: u64) -> ShardIterator {
+        ShardIterator::new_wide(self.finish() as u128, self.count, size)
+    }
+
+    /// Feed `value` through this hasher via [`Hash::hash`](/nightly/std/hash/trait.Hash.html#tymethod.hash)
+    /// and return a [`ShardIterator`](struct.ShardIterator.html) over `size` shard IDs.
+    ///
+    /// This mirrors the stabilized `Hash`/`Hasher` split in `std`, where
+    /// `Hash::hash(&self, &mut H)` drives the hasher, so callers with an
+    /// arbitrary `T: Hash` don't need to manually call `write`/`write_u64`.
+    ///
+    /// Automatically routes through [`into_sized_iter_wide`](#method.into_sized_iter_wide)
+    /// once `count` is past the narrow `u64` path's entropy budget (see
+    /// [`NARROW_COUNT_LIMIT`]), so this ergonomic entry point never silently
+    /// inherits the low-shard-ID bias.
+    pub fn hash_one<T: Hash>(mut self, value: T, size: u64) -> ShardIterator {
+        value.hash(&mut self);
+        if self.count > NARROW_COUNT_LIMIT {
+            self.into_sized_iter_wide(size)
+        } else {
+            self.into_sized_iter(size)
+        }
+    }
 }
 
 impl<H: Hasher + Sized> Hasher for ShardHasher<H> {
@@ -78,30 +144,173 @@ impl<H: Hasher + Sized> IntoIterator for ShardHasher<H> {
     }
 }
 
+/// Builder that produces fresh [`ShardHasher`](struct.ShardHasher.html)s from a shared
+/// [`BuildHasher`](/nightly/std/hash/trait.BuildHasher.html).
+///
+/// This mirrors the way the standard library separates a hashing algorithm
+/// from the keyed state used per-value: a `ShardHashBuilder` holds the
+/// shared state (e.g. a seed) and hands out a new `ShardHasher` for each
+/// value to be hashed, the same way `HashMap` uses its `BuildHasher` to
+/// create a `Hasher` per lookup.
+#[derive(Copy, Clone)]
+pub struct ShardHashBuilder<S: BuildHasher> {
+    count: u64,
+    build_hasher: S,
+}
+
+impl<S: BuildHasher> ShardHashBuilder<S> {
+    /// Create a new `ShardHashBuilder` from a shared `BuildHasher`.
+    ///
+    /// `count` correspond to the total number of shards in the system.
+    pub fn new(count: u64, build_hasher: S) -> Self {
+        Self { count, build_hasher }
+    }
+
+    /// Create a new `ShardHasher` using this builder's `BuildHasher`.
+    pub fn build_hasher(&self) -> ShardHasher<S::Hasher> {
+        ShardHasher::with_hasher(self.count, self.build_hasher.build_hasher())
+    }
+}
+
+/// Hash `value` and return a [`ShardIterator`](struct.ShardIterator.html) over
+/// `size` shard IDs out of `count` total shards.
+///
+/// This is the ergonomic entry point for arbitrary `T: Hash` (strings,
+/// tuples, derived `Hash` structs, ...) that skips the byte-level
+/// `ShardHasher::write*` calls. It automatically uses the widened
+/// permutation path once `count` is past [`NARROW_COUNT_LIMIT`], so the
+/// "load distributed over all shards equally" guarantee holds regardless of
+/// how many shards are configured:
+///
+/// ```rust
+/// use shard_hash::shards_for;
+///
+/// let shards = shards_for(&"user-id", 64, 3).collect::<Vec<u64>>();
+/// assert_eq!(shards.len(), 3);
+/// ```
+pub fn shards_for<T: Hash>(value: T, count: u64, size: u64) -> ShardIterator {
+    ShardHasher::new(count).hash_one(value, size)
+}
+
+/// Tracks which of the `count` shard IDs are still available, supporting
+/// "find the `rank`-th remaining shard" and "remove a shard" without ever
+/// building a `count`-sized structure.
+///
+/// Only the (at most `size`) removed shards are kept, in a sorted `Vec`. The
+/// rank of a remaining shard is recovered by repeatedly correcting for how
+/// many removed shards sit at or below it — the standard fixed-point trick
+/// for "find the k-th element missing from a sparse exclusion list" — so
+/// both operations scale with the number of shards removed so far, not with
+/// `count`. That matters because the primary replica-lookup path
+/// (`shards_for(key, count, size)` with `count` potentially large and `size`
+/// small) would otherwise pay `count`'s cost on every call.
+#[derive(Clone)]
+struct Available {
+    count: u64,
+    removed: Vec<u64>,
+}
+
+impl Available {
+    /// Track availability over `count` shard IDs, all initially available.
+    fn new(count: u64) -> Self {
+        Self {
+            count,
+            removed: Vec::new(),
+        }
+    }
+
+    /// Find the 0-indexed position of the `rank`-th (0-indexed) shard that
+    /// hasn't been removed yet.
+    fn find_by_rank(&self, rank: u64) -> u64 {
+        let mut candidate = rank;
+        loop {
+            let removed_at_or_below = self.removed.partition_point(|&removed| removed <= candidate) as u64;
+            let next = rank + removed_at_or_below;
+            if next == candidate {
+                return candidate;
+            }
+            candidate = next;
+        }
+    }
+
+    /// Mark the shard at `index` (0-indexed) as no longer available.
+    fn remove(&mut self, index: u64) {
+        debug_assert!(index < self.count);
+        let pos = self.removed.partition_point(|&removed| removed < index);
+        self.removed.insert(pos, index);
+    }
+}
+
+/// Odd constant used to mix fresh entropy into a widened permutation state.
+/// Borrowed from the same family of avalanche multipliers as `ahash`/`polars`'
+/// vector hashers (derived from the golden ratio, forced odd).
+const REFILL_CONST: u128 = 0x9E37_79B9_7F4A_7C15_F39C_C060_5CED_C835;
+
 /// Iterator returning shard IDs in preferred query order
-/// 
+///
 /// A `ShardIterator` will not return the same shard ID more than once.
 #[derive(Clone)]
 pub struct ShardIterator {
-    state: u64,
+    state: u128,
+    seed: u64,
+    refills: u64,
+    widened: bool,
     pos: u64,
     min: u64,
-    visited: Vec<u64>,
+    available: Available,
 }
 
 impl ShardIterator {
     /// Create a new `ShardIterator`
-    /// 
+    ///
     /// When `pos` and `size` are equal, this will return a permutation of all
     /// the values between `0` and `pos - 1` based on the `state`.
     pub fn new(state: u64, pos: u64, size: u64) -> Self {
+        Self {
+            state: state as u128,
+            seed: state,
+            refills: 0,
+            widened: false,
+            pos,
+            min: pos-size,
+            available: Available::new(pos),
+        }
+    }
+
+    /// Like [`new`](#method.new), but seeded with a widened `u128` state
+    /// that is topped up with fresh entropy instead of running dry.
+    ///
+    /// A `u64` state only has enough entropy to decompose into `20!`
+    /// distinct permutations; past roughly 20 shards, the later digits of
+    /// `new` are starved and skew toward low shard IDs. This constructor
+    /// keeps `pos`/`size` semantics identical to `new`, but once `state`
+    /// risks running out it is refilled in place by mixing in an
+    /// incrementing counter (see [`refill`](#method.refill)).
+    pub fn new_wide(state: u128, pos: u64, size: u64) -> Self {
         Self {
             state,
+            seed: (state ^ (state >> 64)) as u64,
+            refills: 0,
+            widened: true,
             pos,
             min: pos-size,
-            visited: Vec::with_capacity(size as usize),
+            available: Available::new(pos),
         }
     }
+
+    /// Mix fresh entropy into `state`, combining `seed` with an
+    /// incrementing counter via a rotate/xor/multiply avalanche, the same
+    /// shape `polars`'s vector hasher uses to fold multiple column hashes
+    /// together.
+    fn refill(&mut self) {
+        // `* 2 + 1` maps each `refills` value to a distinct odd multiplier
+        // (unlike `| 1`, which folds every even/odd pair onto the same one),
+        // so successive refills mix in a well-distributed sequence.
+        let next = (self.seed as u128).wrapping_mul((self.refills as u128) * 2 + 1);
+        self.refills += 1;
+        self.state = self.state.rotate_left(17) ^ next;
+        self.state = self.state.wrapping_mul(REFILL_CONST);
+    }
 }
 
 impl Iterator for ShardIterator {
@@ -112,25 +321,30 @@ impl Iterator for ShardIterator {
             return None
         }
 
-        // Calculate the base shard ID
-        let mut ret = self.state % self.pos;
+        // Top up the running state if it no longer has enough entropy for
+        // the remaining permutation digits. Only the widened path refills:
+        // the plain `u64` path keeps its existing (bounded) behavior.
+        if self.widened && self.state < self.pos as u128 {
+            self.refill();
+        }
+
+        // Decompose the next Lehmer code digit: `r` selects the `r`-th
+        // (0-indexed) shard among those still available.
+        let r = (self.state % self.pos as u128) as u64;
 
         // Update internal state
-        self.state /= self.pos;
+        self.state /= self.pos as u128;
         self.pos -= 1;
 
-        // Derive next available value
-        while self.visited.contains(&ret) {
-            ret += 1;
-        }
-        // Save in visited nodes
-        self.visited.push(ret.clone());
+        // Find and remove the r-th (0-indexed) available shard.
+        let ret = self.available.find_by_rank(r);
+        self.available.remove(ret);
 
         Some(ret)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        return (self.pos as usize, Some(self.pos as usize));
+        (self.pos as usize, Some(self.pos as usize))
     }
 }
 
@@ -149,7 +363,7 @@ mod tests {
         sh.write_u64(2237);
         let shards = sh.into_iter().collect::<Vec<u64>>();
 
-        assert_eq!(shards, vec![1, 5, 4, 0, 2, 3, 6]);
+        assert_eq!(shards, vec![1, 6, 5, 0, 3, 2, 4]);
     }
 
     // Static test for 7/1 to prevent alteration to the algorithm
@@ -165,7 +379,7 @@ mod tests {
     fn iterator_7_3() {
         let shards = ShardIterator::new(2237, 7, 3).collect::<Vec<u64>>();
 
-        assert_eq!(shards, vec![4, 1, 3]);
+        assert_eq!(shards, vec![4, 1, 5]);
     }
 
     // Static test for 7/7 to prevent alteration to the algorithm
@@ -173,7 +387,42 @@ mod tests {
     fn iterator_7_7() {
         let shards = ShardIterator::new(2237, 7, 7).collect::<Vec<u64>>();
 
-        assert_eq!(shards, vec![4, 1, 3, 2, 5, 0, 6]);
+        assert_eq!(shards, vec![4, 1, 5, 3, 6, 0, 2]);
+    }
+
+    // Static test for the widened-state path at 40 shards, to prevent
+    // alteration to the refill algorithm.
+    #[test]
+    fn wide_hash_40() {
+        let mut sh = ShardHasher::new(40);
+        sh.write_u64(2237);
+        let shards = sh.into_sized_iter_wide(40).collect::<Vec<u64>>();
+
+        assert_eq!(
+            shards,
+            vec![
+                4, 39, 25, 26, 17, 14, 0, 24, 19, 28, 29, 2, 11, 38, 37, 22, 32, 16, 34, 35, 33,
+                15, 18, 36, 31, 20, 30, 3, 12, 8, 1, 7, 10, 21, 6, 23, 5, 13, 27, 9
+            ]
+        );
+    }
+
+    // Static test for the widened-state path at 40/1, to prevent
+    // alteration to the refill algorithm.
+    #[test]
+    fn wide_iterator_40_1() {
+        let shards = ShardIterator::new_wide(2237, 40, 1).collect::<Vec<u64>>();
+
+        assert_eq!(shards, vec![37]);
+    }
+
+    // Static test for the widened-state path at 40/5, to prevent
+    // alteration to the refill algorithm.
+    #[test]
+    fn wide_iterator_40_5() {
+        let shards = ShardIterator::new_wide(2237, 40, 5).collect::<Vec<u64>>();
+
+        assert_eq!(shards, vec![37, 16, 20, 13, 8]);
     }
 
     // Test that the shards length is equal to the number of replicas
@@ -237,4 +486,98 @@ mod tests {
             assert_eq!(shards2[..], shards[..replicas2 as usize]);
         }
     }
+
+    // Test that the widened-state path returns unique values of the
+    // requested length, mirroring `unique` for the narrow path.
+    #[test]
+    fn wide_unique() {
+        fn has_unique_elements<T>(iter: T) -> bool
+        where
+            T: IntoIterator,
+            T::Item: Eq + Hash,
+        {
+            let mut uniq = HashSet::new();
+            iter.into_iter().all(move |x| uniq.insert(x))
+        }
+
+        for _ in 0..100 {
+            let value: u128 = random();
+            let count = (random::<u64>() % 1024) + 21;
+            let replicas = (random::<u64>() % count) + 1;
+
+            let shards = ShardIterator::new_wide(value, count, replicas).collect::<Vec<u64>>();
+            assert_eq!(shards.len() as u64, replicas);
+            assert!(has_unique_elements(shards));
+        }
+    }
+
+    // Test that the widened-state path preserves the prefix-stability
+    // invariant, mirroring `successive` for the narrow path.
+    #[test]
+    fn wide_successive() {
+        for _ in 0..100 {
+            let value: u128 = random();
+            let count = (random::<u64>() % 1024) + 21;
+            let replicas = (random::<u64>() % count) + 1;
+            let replicas2 = (random::<u64>() % replicas) + 1;
+
+            let shards = ShardIterator::new_wide(value, count, replicas).collect::<Vec<u64>>();
+            let shards2 = ShardIterator::new_wide(value, count, replicas2).collect::<Vec<u64>>();
+            assert_eq!(shards2[..], shards[..replicas2 as usize]);
+        }
+    }
+
+    // Regression test: the narrow `u64` path is known to starve of entropy
+    // once `count` passes roughly 20, since `20!` is the largest factorial a
+    // `u64` covers. Its later digits are then all `0`, so the remaining
+    // shards come out in ascending order and the last-emitted shard skews
+    // toward high IDs. The widened path must not reproduce that bias.
+    #[test]
+    fn wide_reduces_tail_bias() {
+        fn last_shard_chi_square(samples: u64, count: u64, wide: bool) -> f64 {
+            let mut counts = vec![0u64; count as usize];
+            for key in 0..samples {
+                let mut h = ShardHasher::new(count);
+                h.write_u64(key);
+                let iter = if wide {
+                    h.into_sized_iter_wide(count)
+                } else {
+                    h.into_sized_iter(count)
+                };
+                let last = iter.last().unwrap();
+                counts[last as usize] += 1;
+            }
+
+            let total: u64 = counts.iter().sum();
+            let expected = total as f64 / counts.len() as f64;
+            counts
+                .iter()
+                .map(|&observed| {
+                    let diff = observed as f64 - expected;
+                    diff * diff / expected
+                })
+                .sum()
+        }
+
+        const COUNT: u64 = 25;
+        const SAMPLES: u64 = 20_000;
+
+        let narrow_chi_square = last_shard_chi_square(SAMPLES, COUNT, false);
+        let wide_chi_square = last_shard_chi_square(SAMPLES, COUNT, true);
+
+        // Sanity-check the narrow path is indeed the biased case the wide
+        // path needs to fix (critical chi-square for 24 df is ~36.4 at
+        // p=0.95; the narrow path blows well past that).
+        assert!(
+            narrow_chi_square > 10_000.0,
+            "expected narrow path to show strong tail bias, got chi-square {}",
+            narrow_chi_square
+        );
+        // The widened path should stay close to uniform.
+        assert!(
+            wide_chi_square < 200.0,
+            "wide path tail bias too high: chi-square {}",
+            wide_chi_square
+        );
+    }
 }
\ No newline at end of file
@@ -0,0 +1,201 @@
+//! Statistical distribution-quality tests for shard assignment, modeled on
+//! `ahash`'s hash-quality test suite.
+//!
+//! The crate's whole value proposition is that load is spread over all
+//! shards equally without client-side randomness, which only holds if the
+//! configured `Hasher` distributes keys well. These functions measure that:
+//! feed a large sample of keys through [`shards_for`](crate::shards_for) and
+//! check (a) first-choice shard counts are uniform, via [`chi_square`], (b)
+//! flipping a single input bit reshuffles the preferred order, via
+//! [`avalanche_fraction`], and (c) the top-`k` replica sets cover all shards
+//! evenly, via [`replica_coverage_counts`]. Each has a `_with` variant
+//! ([`first_choice_counts_with`], [`last_choice_counts_with`],
+//! [`replica_coverage_counts_with`], [`avalanche_fraction_with`]) taking a
+//! closure building each key's `ShardIterator`, so downstream users can
+//! point the same checks at a custom `Hasher` choice, or at a specific
+//! permutation path (e.g. [`ShardHasher::into_sized_iter`](crate::ShardHasher::into_sized_iter)
+//! vs [`into_sized_iter_wide`](crate::ShardHasher::into_sized_iter_wide)) to
+//! compare them directly. A weak hasher, or a count past
+//! [`NARROW_COUNT_LIMIT`](crate::NARROW_COUNT_LIMIT) routed through the
+//! narrow path, silently breaks the "load distributed over all shards
+//! equally" contract the docs promise.
+
+use crate::{shards_for, ShardIterator};
+
+/// Chi-square statistic for how uniformly `counts` are spread across its
+/// buckets, assuming every bucket's expected count is the sample mean.
+///
+/// A uniform distribution over `k` buckets has `k - 1` degrees of freedom;
+/// compare the result against a chi-square critical value for that many
+/// degrees of freedom at the desired confidence level.
+pub fn chi_square(counts: &[u64]) -> f64 {
+    let total: u64 = counts.iter().sum();
+    let expected = total as f64 / counts.len() as f64;
+    counts
+        .iter()
+        .map(|&observed| {
+            let diff = observed as f64 - expected;
+            diff * diff / expected
+        })
+        .sum()
+}
+
+/// Hash every value in `0..samples` (as `u64` keys), building each
+/// `ShardIterator` via `make_iter`, and tally how often each shard is the
+/// first (most preferred) pick.
+///
+/// Feed the result into [`chi_square`] to check the first-choice shard is
+/// uniformly distributed across `count` shards.
+pub fn first_choice_counts_with<F>(samples: u64, count: u64, mut make_iter: F) -> Vec<u64>
+where
+    F: FnMut(u64) -> ShardIterator,
+{
+    let mut counts = vec![0u64; count as usize];
+    for key in 0..samples {
+        if let Some(shard) = make_iter(key).next() {
+            counts[shard as usize] += 1;
+        }
+    }
+    counts
+}
+
+/// [`first_choice_counts_with`] using [`shards_for`] to build each iterator.
+pub fn first_choice_counts(samples: u64, count: u64) -> Vec<u64> {
+    first_choice_counts_with(samples, count, |key| shards_for(key, count, 1))
+}
+
+/// Hash every value in `0..samples`, building each `ShardIterator` over all
+/// `count` shards via `make_iter`, and tally how often each shard is the
+/// *last* emitted.
+///
+/// The last position is the one most exposed to the narrow permutation
+/// path's entropy starvation past `count > `[`NARROW_COUNT_LIMIT`](crate::NARROW_COUNT_LIMIT):
+/// unlike [`first_choice_counts`], which draws on the full running state and
+/// stays uniform regardless, the last position comes after the starved
+/// state's digits have all dropped to `0`. The remaining shards are then
+/// emitted in ascending order, so the last one skews toward high shard
+/// IDs. Feed the result into [`chi_square`] to check it.
+pub fn last_choice_counts_with<F>(samples: u64, count: u64, mut make_iter: F) -> Vec<u64>
+where
+    F: FnMut(u64) -> ShardIterator,
+{
+    let mut counts = vec![0u64; count as usize];
+    for key in 0..samples {
+        if let Some(shard) = make_iter(key).last() {
+            counts[shard as usize] += 1;
+        }
+    }
+    counts
+}
+
+/// [`last_choice_counts_with`] using [`shards_for`] (over the full `count`
+/// shards) to build each iterator.
+pub fn last_choice_counts(samples: u64, count: u64) -> Vec<u64> {
+    last_choice_counts_with(samples, count, |key| shards_for(key, count, count))
+}
+
+/// Hash every value in `0..samples`, building each `ShardIterator` over the
+/// top `replicas` shards via `make_iter`, and tally, for each shard, how many
+/// keys include it in their picks.
+///
+/// A healthy hasher should cover every shard roughly `samples * replicas /
+/// count` times; feed the result into [`chi_square`] to check that.
+pub fn replica_coverage_counts_with<F>(samples: u64, count: u64, mut make_iter: F) -> Vec<u64>
+where
+    F: FnMut(u64) -> ShardIterator,
+{
+    let mut counts = vec![0u64; count as usize];
+    for key in 0..samples {
+        for shard in make_iter(key) {
+            counts[shard as usize] += 1;
+        }
+    }
+    counts
+}
+
+/// [`replica_coverage_counts_with`] using [`shards_for`] to build each
+/// iterator over its top `replicas` shards.
+pub fn replica_coverage_counts(samples: u64, count: u64, replicas: u64) -> Vec<u64> {
+    replica_coverage_counts_with(samples, count, |key| shards_for(key, count, replicas))
+}
+
+/// Returns the fraction (in `0.0..=1.0`) of single-bit flips of `key` (out of
+/// `u64::BITS`) that change the preferred shard order, building each
+/// (possibly flipped) key's `ShardIterator` via `make_iter`.
+///
+/// This is the avalanche property a hasher needs for shard assignment: two
+/// keys differing by a single bit should not be steered toward the same
+/// shard order. A value close to `0.0` means the hasher barely avalanches
+/// and similar keys will cluster onto the same shards; a healthy hasher
+/// stays close to `1.0`.
+pub fn avalanche_fraction_with<F>(key: u64, mut make_iter: F) -> f64
+where
+    F: FnMut(u64) -> ShardIterator,
+{
+    let baseline = make_iter(key).collect::<Vec<u64>>();
+    let changed = (0..u64::BITS)
+        .filter(|bit| {
+            let flipped = key ^ (1 << bit);
+            make_iter(flipped).collect::<Vec<u64>>() != baseline
+        })
+        .count();
+    changed as f64 / u64::BITS as f64
+}
+
+/// [`avalanche_fraction_with`] using [`shards_for`] (over the full `count`
+/// shards) to build each iterator.
+pub fn avalanche_fraction(key: u64, count: u64) -> f64 {
+    avalanche_fraction_with(key, |key| shards_for(key, count, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ShardHasher;
+    use std::hash::Hasher;
+
+    // A uniform distribution should keep the chi-square statistic well
+    // within the critical value for its degrees of freedom; a generous
+    // tolerance avoids a flaky test on a merely unlucky sample.
+    #[test]
+    fn first_choice_is_uniform() {
+        let counts = first_choice_counts(20_000, 16);
+        assert!(chi_square(&counts) < 50.0);
+    }
+
+    #[test]
+    fn replica_coverage_is_uniform() {
+        let counts = replica_coverage_counts(5_000, 16, 3);
+        assert!(chi_square(&counts) < 50.0);
+    }
+
+    // `shards_for` auto-routes through the widened permutation path once
+    // `count` exceeds `NARROW_COUNT_LIMIT`; this guards that the "equal
+    // load" guarantee actually holds there, not just for small `count`.
+    #[test]
+    fn last_choice_is_uniform_for_large_shard_counts() {
+        let counts = last_choice_counts(20_000, 40);
+        assert!(chi_square(&counts) < 200.0);
+    }
+
+    // Regression guard: confirms the narrow path (used directly, bypassing
+    // `shards_for`'s routing) is indeed the biased case that routing must
+    // avoid once `count` is large. If this ever stops being biased, the
+    // "wide" routing is no longer needed; if it ever passes for `shards_for`
+    // itself, the routing has regressed.
+    #[test]
+    fn narrow_path_last_choice_is_biased_for_large_shard_counts() {
+        let counts = last_choice_counts_with(20_000, 40, |key| {
+            let mut h = ShardHasher::new(40);
+            h.write_u64(key);
+            h.into_sized_iter(40)
+        });
+        assert!(chi_square(&counts) > 5_000.0);
+    }
+
+    #[test]
+    fn single_bit_flip_reshuffles_order() {
+        let average = (0..200).map(|key| avalanche_fraction(key, 16)).sum::<f64>() / 200.0;
+        assert!(average > 0.9, "avalanche fraction too low: {}", average);
+    }
+}